@@ -2,13 +2,15 @@ use std::{
     collections::{HashMap, HashSet, VecDeque},
     env,
     error::Error,
-    fs::{File, read_dir},
-    io::{BufRead, BufReader},
+    fs::{self, read_dir},
     path::{Path, PathBuf},
     process::{Command, exit},
     str,
 };
 
+use object::read::archive::ArchiveFile;
+use object::{Object, ObjectSymbol};
+
 const LLVM_MAJOR_VERSION: usize = 20;
 
 fn main() {
@@ -43,11 +45,67 @@ struct BuiltQwertyMlir {
     include_dir: PathBuf,
     lib_dir: PathBuf,
     bin_dir: PathBuf,
-    static_lib_names: Vec<String>,
-    mlir_deps_graph: HashMap<String, Vec<String>>,
+    dynamic: bool,
+}
+
+// Prefixes of the qwerty/qwutil/tweedledum libraries that
+// build_qwerty_mlir() produces in lib_dir. In static mode the link order is
+// derived from archive symbols in run_bindgen() (see archive_link_groups()).
+// In dynamic mode the libraries are emitted in the order listed here, which
+// is a valid dependency order for the static archives used when no shared
+// variant exists.
+const QWERTY_LIB_PREFIXES: [&str; 9] = [
+    "libMLIRCAPIQwerty",
+    "libMLIRQwerty",
+    "libqwutil",
+    "libtweedledum",
+    "libMLIRCAPIUtils",
+    "libMLIRCAPIQCirc",
+    "libMLIRCAPICCirc",
+    "libMLIRQCirc",
+    "libMLIRCCirc",
+];
+
+// Whether to link LLVM/MLIR (and our own qwerty/qwutil/tweedledum libs,
+// where cmake built shared variants of them) dynamically instead of
+// statically. Static linking is the default because
+// it produces a self-contained binary, but on large MLIR builds it makes for
+// an enormous, slow link step, so this is offered as an opt-in, mirroring
+// the `prefer-dynamic` flag rustc's own bootstrap uses.
+fn prefer_dynamic() -> bool {
+    env::var_os("CARGO_FEATURE_DYNAMIC").is_some()
+        || env::var_os("QWERTY_MLIR_PREFER_DYNAMIC").is_some()
+}
+
+// Asks cmake to build position-independent qwerty/qwutil/tweedledum
+// archives, so a downstream crate can embed qwerty-mlir-sys in a cdylib (or
+// build on a 32-bit target) without relinking against our static archives
+// failing with relocation errors. PIC is on unconditionally by default:
+// Cargo has no build-script-visible signal for "the final artifact will be a
+// cdylib" (CARGO_CFG_* only covers `rustc --print cfg` keys, which stop at
+// target_arch/target_os/target_env/etc. -- there's no relocation-model
+// entry), so rather than pretend to pick a target-aware default, we just pay
+// the small PIC cost everywhere and let QWERTY_MLIR_PIC=0 opt back out for
+// anyone who cares.
+fn configure_pic(cmake_config: &mut cmake::Config) {
+    let want_pic = env::var("QWERTY_MLIR_PIC").as_deref() != Ok("0");
+
+    if !want_pic {
+        return;
+    }
+
+    cmake_config.define("CMAKE_POSITION_INDEPENDENT_CODE", "ON");
+
+    // i686 doesn't default to position-independent code the way x86_64
+    // does, so ask for it explicitly too -- the same fix rustc restored for
+    // i686 native compiles.
+    if env::var("CARGO_CFG_TARGET_ARCH").as_deref() == Ok("x86") {
+        cmake_config.define("CMAKE_CXX_FLAGS", "-fPIC");
+    }
 }
 
 fn build_qwerty_mlir() -> BuiltQwertyMlir {
+    let dynamic = prefer_dynamic();
     let parent_dir = PathBuf::from("..");
 
     let rerun_if_changed = vec![
@@ -57,17 +115,17 @@ fn build_qwerty_mlir() -> BuiltQwertyMlir {
         parent_dir.join("tweedledum"),
     ];
 
-    let install_dir = cmake::Config::new(parent_dir)
+    let mut cmake_config = cmake::Config::new(parent_dir);
+    cmake_config
         .generator("Ninja")
         // Hide a wall of warnings that are from LLVM, not us
         // TODO: remove this so we don't miss useful warnings
-        .configure_arg("-Wno-dev")
-        .define("DUMP_MLIR_DEPS", "ON")
-        .build();
+        .configure_arg("-Wno-dev");
+    configure_pic(&mut cmake_config);
+    let install_dir = cmake_config.build();
     let include_dir = install_dir.join("include");
     let lib_dir = install_dir.join("lib");
     let bin_dir = install_dir.join("bin");
-    let mlir_deps_tsv_path = install_dir.join("lib").join("mlir-deps.tsv");
 
     // Check if include_dir is empty
     for (nonempty_dir, contents_summary) in [
@@ -83,58 +141,12 @@ fn build_qwerty_mlir() -> BuiltQwertyMlir {
         }
     }
 
-    // We have to be careful with the ordering of linker args here. We need to
-    // pass a topological ordering of this dependency graph:
-    //
-    //     libMLIRCAPIQwerty.a
-    //           |
-    //           V
-    //     libMLIRQwerty*.a
-    //           |
-    //           V
-    //       libqwutil.a ----> libtweedledum.a
-    //           |
-    //           |   libMLIRCAPIQCirc.a
-    //           |      |
-    //           V      V
-    //      libMLIRQCirc*.a
-    //
-    // We choose the following topological ordering:
-    // libMLIRCAPIQwerty.a, libMLIRQwerty*.a, libqwutil.a, libtweedledum.a,
-    // libMLIRCAPIQCirc.a, libMLIRQCirc*.a.
-
-    let mut static_lib_names = lib_names_starting_with(&lib_dir, "libMLIRCAPIQwerty");
-    static_lib_names.append(&mut lib_names_starting_with(&lib_dir, "libMLIRQwerty"));
-    static_lib_names.append(&mut lib_names_starting_with(&lib_dir, "libqwutil"));
-    static_lib_names.append(&mut lib_names_starting_with(&lib_dir, "libtweedledum"));
-    static_lib_names.append(&mut lib_names_starting_with(&lib_dir, "libMLIRCAPIUtils"));
-    static_lib_names.append(&mut lib_names_starting_with(&lib_dir, "libMLIRCAPIQCirc"));
-    static_lib_names.append(&mut lib_names_starting_with(&lib_dir, "libMLIRCAPICCirc"));
-    static_lib_names.append(&mut lib_names_starting_with(&lib_dir, "libMLIRQCirc"));
-    static_lib_names.append(&mut lib_names_starting_with(&lib_dir, "libMLIRCCirc"));
-
-    // For an explanation of what mlir-deps.tsv is, see CMakeLists.txt in the
-    // parent repository.
-    let mut mlir_deps_graph = HashMap::<String, Vec<String>>::new();
-    let mlir_deps_tsv_fp = File::open(mlir_deps_tsv_path).unwrap();
-    for mlir_deps_line_res in BufReader::new(mlir_deps_tsv_fp).lines() {
-        let mlir_deps_line = mlir_deps_line_res.unwrap();
-        let mut cols: Vec<String> = mlir_deps_line
-            .trim()
-            .split('\t')
-            .map(String::from)
-            .collect();
-        let depender = cols.remove(0);
-        mlir_deps_graph.insert(depender, cols);
-    }
-
     BuiltQwertyMlir {
         rerun_if_changed,
         include_dir,
         lib_dir,
         bin_dir,
-        static_lib_names,
-        mlir_deps_graph,
+        dynamic,
     }
 }
 
@@ -154,51 +166,59 @@ fn run_bindgen(built_qwerty_mlir: BuiltQwertyMlir) -> Result<(), Box<dyn Error>>
         "cargo:rustc-link-search={}",
         built_qwerty_mlir.lib_dir.display()
     );
-    for qwerty_lib_name in built_qwerty_mlir.static_lib_names {
-        if let Some(name) = parse_archive_name(&qwerty_lib_name) {
-            println!("cargo:rustc-link-lib=static={name}");
-        }
-    }
-
     println!("cargo:rustc-link-search={}", llvm_config("--libdir")?);
 
-    let mlir_lib_names: HashSet<_> = lib_names_starting_with(llvm_config("--libdir")?, "libMLIR")
-        .iter()
-        .filter_map(|s| parse_archive_name(s).map(str::to_string))
-        .collect();
-    for mlir_lib_name in toposort(&built_qwerty_mlir.mlir_deps_graph) {
-        if mlir_lib_names.contains(&mlir_lib_name) {
-            println!("cargo:rustc-link-lib=static={mlir_lib_name}");
+    if built_qwerty_mlir.dynamic {
+        // One dylib link each for MLIR and LLVM covers every MLIR/LLVM
+        // symbol; shared objects resolve their own dependencies, so there is
+        // no per-component link line to build for them. Our own libs are
+        // emitted in QWERTY_LIB_PREFIXES order, as shared objects where cmake
+        // built them and as static archives otherwise.
+        for prefix in QWERTY_LIB_PREFIXES {
+            let (qwerty_lib_names, kind) =
+                lib_names_starting_with(&built_qwerty_mlir.lib_dir, prefix, true);
+            let link_kind = kind.as_rustc_link_lib_kind();
+            for qwerty_lib_name in qwerty_lib_names {
+                if let Some(name) = parse_lib_name(&qwerty_lib_name) {
+                    println!("cargo:rustc-link-lib={link_kind}={name}");
+                }
+            }
         }
-    }
+        println!("cargo:rustc-link-lib=dylib=MLIR");
+        println!("cargo:rustc-link-lib=dylib=LLVM");
+    } else {
+        let llvm_lib_dir = PathBuf::from(llvm_config("--libdir")?);
+        let archive_groups = archive_link_groups(&built_qwerty_mlir.lib_dir, &llvm_lib_dir);
+        emit_archive_link_groups(&archive_groups);
 
-    for name in llvm_config("--libnames")?.trim().split(' ') {
-        if let Some(name) = parse_archive_name(name) {
-            println!("cargo:rustc-link-lib={name}");
+        for name in llvm_config("--libnames")?.trim().split(' ') {
+            if let Some(name) = parse_lib_name(name) {
+                println!("cargo:rustc-link-lib={name}");
+            }
         }
-    }
 
-    for flag in llvm_config("--system-libs")?.trim().split(' ') {
-        let flag = flag.trim_start_matches("-l");
-
-        if flag.starts_with('/') {
-            // llvm-config returns absolute paths for dynamically linked libraries.
-            let path = Path::new(flag);
-
-            println!(
-                "cargo:rustc-link-search={}",
-                path.parent().unwrap().display()
-            );
-            println!(
-                "cargo:rustc-link-lib={}",
-                path.file_stem()
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .trim_start_matches("lib")
-            );
-        } else {
-            println!("cargo:rustc-link-lib={flag}");
+        for flag in llvm_config("--system-libs")?.trim().split(' ') {
+            let flag = flag.trim_start_matches("-l");
+
+            if flag.starts_with('/') {
+                // llvm-config returns absolute paths for dynamically linked libraries.
+                let path = Path::new(flag);
+
+                println!(
+                    "cargo:rustc-link-search={}",
+                    path.parent().unwrap().display()
+                );
+                println!(
+                    "cargo:rustc-link-lib={}",
+                    path.file_stem()
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                        .trim_start_matches("lib")
+                );
+            } else {
+                println!("cargo:rustc-link-lib={flag}");
+            }
         }
     }
 
@@ -220,21 +240,59 @@ fn run_bindgen(built_qwerty_mlir: BuiltQwertyMlir) -> Result<(), Box<dyn Error>>
     Ok(())
 }
 
-fn lib_names_starting_with<P: AsRef<Path>>(dir: P, prefix: &str) -> Vec<String> {
+// Whether lib_names_starting_with() found shared objects or static archives.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LibKind {
+    Static,
+    Dynamic,
+}
+
+impl LibKind {
+    fn as_rustc_link_lib_kind(self) -> &'static str {
+        match self {
+            LibKind::Static => "static",
+            LibKind::Dynamic => "dylib",
+        }
+    }
+}
+
+// Lists the libraries in `dir` whose file name starts with `prefix`, along
+// with whether they're shared objects or static archives. When
+// `prefer_dynamic` is set and cmake produced `.so`/`.dylib` variants, those
+// are returned in favor of the `.a` variants; otherwise (or if no shared
+// variant exists) the static archives are returned.
+fn lib_names_starting_with<P: AsRef<Path>>(
+    dir: P,
+    prefix: &str,
+    prefer_dynamic: bool,
+) -> (Vec<String>, LibKind) {
     let dir_path = dir.as_ref();
-    let lib_paths: Vec<_> = read_dir(dir_path)
-        .unwrap()
-        .filter_map(|dirent| {
-            dirent
-                .unwrap()
-                .file_name()
-                .to_str()
-                .filter(|filename| filename.starts_with(prefix))
-                .map(|s| s.to_string())
-        })
-        .collect();
+    let mut static_lib_names = Vec::new();
+    let mut dynamic_lib_names = Vec::new();
+
+    for dirent in read_dir(dir_path).unwrap() {
+        let file_name = dirent.unwrap().file_name();
+        let Some(filename) = file_name.to_str() else {
+            continue;
+        };
+        if !filename.starts_with(prefix) {
+            continue;
+        }
 
-    if lib_paths.is_empty() {
+        if filename.ends_with(".so") || filename.ends_with(".dylib") {
+            dynamic_lib_names.push(filename.to_string());
+        } else if filename.ends_with(".a") {
+            static_lib_names.push(filename.to_string());
+        }
+    }
+
+    let (lib_names, kind) = if prefer_dynamic && !dynamic_lib_names.is_empty() {
+        (dynamic_lib_names, LibKind::Dynamic)
+    } else {
+        (static_lib_names, LibKind::Static)
+    };
+
+    if lib_names.is_empty() {
         panic!(
             "Could not find libraries starting with {} in directory {}",
             prefix,
@@ -242,7 +300,7 @@ fn lib_names_starting_with<P: AsRef<Path>>(dir: P, prefix: &str) -> Vec<String>
         );
     }
 
-    lib_paths
+    (lib_names, kind)
 }
 
 fn get_system_libcpp() -> Option<&'static str> {
@@ -265,8 +323,9 @@ fn llvm_config(argument: &str) -> Result<String, Box<dyn Error>> {
         "llvm-config"
     };
 
+    let link_static_flag = if prefer_dynamic() { "" } else { "--link-static " };
     let call = format!(
-        "{} --link-static {argument}",
+        "{} {link_static_flag}{argument}",
         prefix.join(llvm_config_exe).display(),
     );
 
@@ -282,12 +341,275 @@ fn llvm_config(argument: &str) -> Result<String, Box<dyn Error>> {
     .to_string())
 }
 
-fn parse_archive_name(name: &str) -> Option<&str> {
-    if let Some(name) = name.strip_prefix("lib") {
-        name.strip_suffix(".a")
-    } else {
-        None
+fn parse_lib_name(name: &str) -> Option<&str> {
+    let name = name.strip_prefix("lib")?;
+    [".a", ".so", ".dylib"]
+        .into_iter()
+        .find_map(|suffix| name.strip_suffix(suffix))
+}
+
+// A run of archives to pass to the linker, in the order they should appear
+// on the link line.
+enum LinkGroup {
+    // An archive with no cyclic dependency on any other archive we're
+    // linking.
+    Single(String),
+    // A strongly-connected set of archives that depend on each other. These
+    // need linker-level grouping (see emit_archive_link_groups()) since no
+    // linear order resolves every symbol in one pass.
+    Cyclic(Vec<String>),
+}
+
+// Figures out a valid static link order for the `.a` archives in
+// `qwerty_lib_dir` (our own qwerty/qwutil/tweedledum libs) together with the
+// `libMLIR*.a` archives in `llvm_lib_dir`, by inspecting the symbols each
+// archive defines and imports rather than relying on a hand-maintained order.
+// MLIR's static libraries frequently have dependency cycles, so archives
+// that belong to one are grouped together instead of dropped.
+fn archive_link_groups(qwerty_lib_dir: &Path, llvm_lib_dir: &Path) -> Vec<LinkGroup> {
+    let mut archive_paths = Vec::new();
+    for prefix in QWERTY_LIB_PREFIXES {
+        let (names, _) = lib_names_starting_with(qwerty_lib_dir, prefix, false);
+        for name in names {
+            archive_paths.push(qwerty_lib_dir.join(name));
+        }
+    }
+    let (mlir_archive_names, _) = lib_names_starting_with(llvm_lib_dir, "libMLIR", false);
+    for name in mlir_archive_names {
+        archive_paths.push(llvm_lib_dir.join(name));
+    }
+
+    let graph = archive_dep_graph(&archive_paths);
+    let components = tarjan_scc(&graph);
+
+    let mut component_of = HashMap::new();
+    for (component_idx, component) in components.iter().enumerate() {
+        for name in component {
+            component_of.insert(name.clone(), component_idx);
+        }
+    }
+
+    let mut condensed: HashMap<String, Vec<String>> = HashMap::new();
+    for (name, dependees) in &graph {
+        let component_idx = component_of[name];
+        let edges = condensed.entry(component_idx.to_string()).or_default();
+        for dependee in dependees {
+            let dependee_component_idx = component_of[dependee];
+            if dependee_component_idx != component_idx {
+                let dependee_component_name = dependee_component_idx.to_string();
+                if !edges.contains(&dependee_component_name) {
+                    edges.push(dependee_component_name);
+                }
+            }
+        }
+    }
+
+    toposort(&condensed)
+        .into_iter()
+        .map(|component_idx| {
+            let component = &components[component_idx.parse::<usize>().unwrap()];
+            let has_self_loop = component
+                .iter()
+                .any(|name| graph[name].iter().any(|dependee| dependee == name));
+            if component.len() > 1 || has_self_loop {
+                LinkGroup::Cyclic(component.clone())
+            } else {
+                LinkGroup::Single(component[0].clone())
+            }
+        })
+        .collect()
+}
+
+// Finds the strongly-connected components of `graph` using Tarjan's
+// algorithm.
+fn tarjan_scc(graph: &HashMap<String, Vec<String>>) -> Vec<Vec<String>> {
+    struct Tarjan<'a> {
+        graph: &'a HashMap<String, Vec<String>>,
+        index_of: HashMap<String, usize>,
+        lowlink_of: HashMap<String, usize>,
+        on_stack: HashSet<String>,
+        stack: Vec<String>,
+        next_index: usize,
+        components: Vec<Vec<String>>,
+    }
+
+    impl Tarjan<'_> {
+        fn visit(&mut self, node: &str) {
+            self.index_of.insert(node.to_string(), self.next_index);
+            self.lowlink_of.insert(node.to_string(), self.next_index);
+            self.next_index += 1;
+            self.stack.push(node.to_string());
+            self.on_stack.insert(node.to_string());
+
+            if let Some(dependees) = self.graph.get(node) {
+                for dependee in dependees.clone() {
+                    if !self.index_of.contains_key(&dependee) {
+                        self.visit(&dependee);
+                        let lowlink = self.lowlink_of[node].min(self.lowlink_of[&dependee]);
+                        self.lowlink_of.insert(node.to_string(), lowlink);
+                    } else if self.on_stack.contains(&dependee) {
+                        let lowlink = self.lowlink_of[node].min(self.index_of[&dependee]);
+                        self.lowlink_of.insert(node.to_string(), lowlink);
+                    }
+                }
+            }
+
+            if self.lowlink_of[node] == self.index_of[node] {
+                let mut component = Vec::new();
+                loop {
+                    let member = self.stack.pop().unwrap();
+                    self.on_stack.remove(&member);
+                    let is_root = member == node;
+                    component.push(member);
+                    if is_root {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        graph,
+        index_of: HashMap::new(),
+        lowlink_of: HashMap::new(),
+        on_stack: HashSet::new(),
+        stack: Vec::new(),
+        next_index: 0,
+        components: Vec::new(),
+    };
+
+    for node in graph.keys() {
+        if !tarjan.index_of.contains_key(node) {
+            tarjan.visit(node);
+        }
+    }
+
+    tarjan.components
+}
+
+// Emits the static archives in `groups`, in order, onto the link line.
+//
+// Cargo doesn't interleave `cargo:rustc-link-lib` and `cargo:rustc-link-arg`
+// directives in the final linker invocation: it batches every
+// `rustc-link-lib` into one early block and every `rustc-link-arg` into a
+// separate block appended at the very end. If `Single` archives went out as
+// `rustc-link-lib=static=` while `Cyclic` groups went out as
+// `rustc-link-arg` (for `--start-group`/`--end-group`), the cyclic group
+// would land stranded after everything else, breaking the very ordering
+// `archive_link_groups` computed. So on ELF, every archive here -- singles
+// and cyclic members alike -- goes out as `rustc-link-arg`, keeping them in
+// one contiguous, correctly-ordered block, wrapped in
+// `-Wl,-Bstatic`/`-Wl,-Bdynamic` so a library that also has a `.so` variant
+// still resolves to the `.a`. ld64 and link.exe support neither linker
+// groups nor `-Bstatic`/`-Bdynamic`, so on macOS/MSVC we keep using
+// `rustc-link-lib=static=` (which already forces static resolution there)
+// for every archive, approximating a cycle by repeating its members.
+fn emit_archive_link_groups(groups: &[LinkGroup]) {
+    if cfg!(any(target_os = "macos", target_env = "msvc")) {
+        for group in groups {
+            match group {
+                LinkGroup::Single(archive_name) => {
+                    println!("cargo:rustc-link-lib=static={archive_name}");
+                }
+                LinkGroup::Cyclic(archive_names) => {
+                    for archive_name in archive_names.iter().chain(archive_names) {
+                        println!("cargo:rustc-link-lib=static={archive_name}");
+                    }
+                }
+            }
+        }
+        return;
     }
+
+    println!("cargo:rustc-link-arg=-Wl,-Bstatic");
+    for group in groups {
+        match group {
+            LinkGroup::Single(archive_name) => println!("cargo:rustc-link-arg=-l{archive_name}"),
+            LinkGroup::Cyclic(archive_names) => {
+                println!("cargo:rustc-link-arg=-Wl,--start-group");
+                for archive_name in archive_names {
+                    println!("cargo:rustc-link-arg=-l{archive_name}");
+                }
+                println!("cargo:rustc-link-arg=-Wl,--end-group");
+            }
+        }
+    }
+    println!("cargo:rustc-link-arg=-Wl,-Bdynamic");
+}
+
+// Builds a dependency graph of `A -> B` edges, where archive `A` imports a
+// symbol that archive `B` defines, so `A` must be linked before `B`.
+fn archive_dep_graph(archive_paths: &[PathBuf]) -> HashMap<String, Vec<String>> {
+    // Sorted so that if a symbol is (erroneously) defined in more than one
+    // archive, the tie always resolves to the same archive instead of
+    // depending on read_dir()'s unspecified iteration order.
+    let mut archive_paths = archive_paths.to_vec();
+    archive_paths.sort();
+
+    let archives: Vec<(String, HashSet<String>, HashSet<String>)> = archive_paths
+        .iter()
+        .filter_map(|path| {
+            let name = parse_lib_name(path.file_name()?.to_str()?)?;
+            let (defined, undefined) = read_archive_symbols(path);
+            Some((name.to_string(), defined, undefined))
+        })
+        .collect();
+
+    let mut symbol_owners: HashMap<&str, &str> = HashMap::new();
+    for (name, defined, _) in &archives {
+        for symbol in defined {
+            symbol_owners.entry(symbol).or_insert(name);
+        }
+    }
+
+    let mut graph = HashMap::new();
+    for (name, _, undefined) in &archives {
+        let dependees: &mut Vec<String> = graph.entry(name.clone()).or_default();
+        for symbol in undefined {
+            if let Some(&owner) = symbol_owners.get(symbol.as_str()) {
+                if owner != name && !dependees.iter().any(|dependee| dependee == owner) {
+                    dependees.push(owner.to_string());
+                }
+            }
+        }
+    }
+
+    graph
+}
+
+// Returns the (defined, undefined) global symbol names across every object
+// file in the archive at `path`.
+fn read_archive_symbols(path: &Path) -> (HashSet<String>, HashSet<String>) {
+    let data = fs::read(path).unwrap();
+    let archive = ArchiveFile::parse(data.as_slice())
+        .unwrap_or_else(|err| panic!("failed to parse archive {}: {err}", path.display()));
+
+    let mut defined = HashSet::new();
+    let mut undefined = HashSet::new();
+    for member in archive.members() {
+        let member = member.unwrap();
+        let Ok(member_data) = member.data(data.as_slice()) else {
+            continue;
+        };
+        let Ok(object_file) = object::File::parse(member_data) else {
+            continue;
+        };
+        for symbol in object_file.symbols() {
+            if !symbol.is_global() {
+                continue;
+            }
+            let Ok(name) = symbol.name() else { continue };
+            if symbol.is_undefined() {
+                undefined.insert(name.to_string());
+            } else {
+                defined.insert(name.to_string());
+            }
+        }
+    }
+
+    (defined, undefined)
 }
 
 fn toposort(graph: &HashMap<String, Vec<String>>) -> Vec<String> {